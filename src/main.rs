@@ -3,16 +3,15 @@ mod luabins;
 mod read;
 mod save;
 use save::UncompressedSize;
-use rng::SggPcg;
-use rand::RngCore;
+use rng::{RandomSource, SggPcg};
 use structopt::StructOpt;
-use rlua::{Lua, Variadic, Value};
+use rlua::{AnyUserData, Context, Lua, Scope, UserData, Variadic, Value};
+use std::collections::HashMap;
 use std::fs;
 use std::rc::Rc;
 use std::cell::RefCell;
-use libm::ldexp;
 use lz4;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 #[derive(StructOpt)]
@@ -21,10 +20,109 @@ struct Cli {
   hades_scripts_dir: std::path::PathBuf,
   #[structopt(short = "f", long)]
   hades_save_file: std::path::PathBuf,
+  // The gaussian PCG is seeded once at startup and never touched by randomseed/randomsynchronize,
+  // so this has to be calibrated against observed game behavior rather than derived from the save seed.
+  #[structopt(long, default_value = "0")]
+  gaussian_seed: u64,
+  // When set, re-runs the script once per seed in START..END (END exclusive) instead of once
+  // with the main stream seeded to 0.
+  #[structopt(long)]
+  seed_range: Option<String>,
+  #[structopt(long, default_value = "1")]
+  threads: usize,
   #[structopt(parse(from_os_str))]
   script: std::path::PathBuf
 }
 
+struct GaussState {
+  has_value: bool,
+  value: f64
+}
+
+// Models the game's RandomSynchronize: the default/nil id always maps to the main
+// stream, while every other id lazily gets its own generator, seeded with 0 until
+// randomsynchronize reseeds that id.
+struct RngStreams {
+  main: SggPcg,
+  named: HashMap<String, SggPcg>
+}
+
+impl RngStreams {
+  fn new(seed: u64) -> Self {
+    RngStreams { main: SggPcg::new(seed), named: HashMap::new() }
+  }
+
+  fn stream(&mut self, id: Option<String>) -> &mut SggPcg {
+    match id {
+      None => &mut self.main,
+      Some(name) => self.named.entry(name).or_insert_with(|| SggPcg::new(0))
+    }
+  }
+
+  fn synchronize(&mut self, id: Option<String>, seed: u64) {
+    match id {
+      None => self.main = SggPcg::new(seed),
+      Some(name) => { self.named.insert(name, SggPcg::new(seed)); }
+    }
+  }
+
+  fn ids(&self) -> Vec<String> {
+    self.named.keys().cloned().collect()
+  }
+
+  fn snapshot(&self) -> RngStreamsSnapshot {
+    RngStreamsSnapshot {
+      main: self.main.get_state(),
+      named: self.named.iter().map(|(id, rng)| (id.clone(), rng.get_state())).collect()
+    }
+  }
+
+  fn restore(&mut self, snapshot: &RngStreamsSnapshot) {
+    self.main.set_state(snapshot.main);
+    self.named = snapshot.named.iter().map(|(id, state)| {
+      let mut rng = SggPcg::new(0);
+      rng.set_state(*state);
+      (id.clone(), rng)
+    }).collect();
+  }
+}
+
+struct RngStreamsSnapshot {
+  main: rng::PcgState,
+  named: HashMap<String, rng::PcgState>
+}
+
+// Opaque snapshot handed to Lua so a script can checkpoint RNG state before exploring
+// a branch of the route search and restore it before exploring the next one, without
+// paying to resimulate the run from the top seed.
+struct RngSnapshot {
+  streams: RngStreamsSnapshot,
+  gaussian: rng::PcgState,
+  gaussian_has_value: bool,
+  gaussian_value: f64
+}
+
+impl UserData for RngSnapshot {}
+
+// A module name may be dotted (RoomManager.Helpers) or slash-separated
+// (RoomManager/Helpers), matching the old bare Import(path) callers that already
+// spelled out the ".lua" suffix themselves, so strip it before re-appending it.
+fn resolve_module_path(scripts_dir: &Path, module_name: &str) -> PathBuf {
+  let trimmed = module_name.strip_suffix(".lua").unwrap_or(module_name);
+  scripts_dir.join(format!("{}.lua", trimmed.replace('.', "/")))
+}
+
+// Lua ids are usually strings but the engine doesn't guarantee it, so stringify
+// whatever comparable scalar comes in; anything else falls back to the main stream.
+fn rng_id(id: Value) -> Option<String> {
+  match id {
+    Value::String(s) => s.to_str().ok().map(|s| s.to_string()),
+    Value::Integer(i) => Some(i.to_string()),
+    Value::Number(n) => Some(n.to_string()),
+    _ => None
+  }
+}
+
 #[derive(Debug)]
 enum Error {
   Lua {
@@ -32,6 +130,9 @@ enum Error {
   },
   IO {
     error: std::io::Error
+  },
+  Cli {
+    message: String
   }
 }
 
@@ -53,166 +154,398 @@ impl From<Error> for rlua::Error {
   fn from(error: Error) -> Self {
      match error {
        Error::Lua { error } => error,
-       Error::IO { error } => rlua::Error::ExternalError(Arc::new(error))
+       Error::IO { error } => rlua::Error::ExternalError(Arc::new(error)),
+       Error::Cli { message } => rlua::Error::RuntimeError(message)
      }
   }
 }
 
+// Registers the Import hook and the RNG hooks (randomseed/randomsynchronize/randomint/
+// random/randomgaussian/RngStreamIds/RngSnapshot/RngRestore) against a fresh context.
+// Shared between the single-run path and each seed-search worker so they stay in sync.
+fn install_hooks<'lua, 'scope>(
+  lua_ctx: Context<'lua>,
+  scope: &Scope<'lua, 'scope>,
+  parent_path: PathBuf,
+  rng_streams: &'scope Rc<RefCell<RngStreams>>,
+  gaussian_rng: &'scope Rc<RefCell<SggPcg>>,
+  gaussian_state: &'scope Rc<RefCell<GaussState>>,
+  modules: &'scope Rc<RefCell<HashMap<String, Value<'lua>>>>,
+  loading: &'scope Rc<RefCell<Vec<String>>>
+) -> Result<()> {
+    let import = scope.create_function(move |inner_lua_ctx, module_name: String| {
+        let resolved = resolve_module_path(&parent_path, &module_name);
+        let key = resolved.to_string_lossy().into_owned();
+
+        if let Some(cached) = modules.borrow().get(&key) {
+          return Ok(cached.clone());
+        }
+
+        if loading.borrow().iter().any(|m| m == &key) {
+          let mut cycle = loading.borrow().clone();
+          cycle.push(key.clone());
+          return Err(rlua::Error::RuntimeError(format!("Import cycle detected: {}", cycle.join(" -> "))));
+        }
+
+        loading.borrow_mut().push(key.clone());
+        let result: rlua::Result<Value> = (|| {
+          let source = read_file(&resolved).map_err(rlua::Error::from)?;
+          inner_lua_ctx.load(&source).eval::<Value>()
+        })();
+        loading.borrow_mut().pop();
+
+        let value = result?;
+        modules.borrow_mut().insert(key, value.clone());
+        Ok(value)
+    })?;
+    lua_ctx.globals().set("Import", import)?;
+    // Hooks into the engine for RNG
+    let randomseed = scope.create_function(move |_, (o_seed, _id): (Option<u32>, Value) | {
+        let seed = match o_seed {
+            Some(s) => s,
+            None => 0
+        };
+        rng_streams.borrow_mut().synchronize(None, seed as u64);
+        Ok(())
+    })?;
+    lua_ctx.globals().set("randomseed", randomseed)?;
+    let randomsynchronize = scope.create_function(move |_, (id, o_seed): (Value, Option<u32>)| {
+        let seed = match o_seed {
+            Some(s) => s,
+            None => 0
+        };
+        rng_streams.borrow_mut().synchronize(rng_id(id), seed as u64);
+        Ok(())
+    })?;
+    lua_ctx.globals().set("randomsynchronize", randomsynchronize)?;
+    let randomint = scope.create_function(move |_, (min, max, id): (i32, i32, Value)| {
+        let mut streams = rng_streams.borrow_mut();
+        Ok(streams.stream(rng_id(id)).bounded_i32(min, max))
+    })?;
+    lua_ctx.globals().set("randomint", randomint)?;
+    let random = scope.create_function(move |_, id: Value| {
+        let mut streams = rng_streams.borrow_mut();
+        Ok(streams.stream(rng_id(id)).next_f64())
+    })?;
+    lua_ctx.globals().set("random", random)?;
+    let rng_stream_ids = scope.create_function(move |inner_lua_ctx, _args: Variadic<Value>| {
+        inner_lua_ctx.create_sequence_from(rng_streams.borrow().ids())
+    })?;
+    lua_ctx.globals().set("RngStreamIds", rng_stream_ids)?;
+    let randomgaussian = scope.create_function(move |_, _args: Variadic<Value>| {
+        let mut rng = gaussian_rng.borrow_mut();
+        let mut state = gaussian_state.borrow_mut();
+        Ok(rand_gauss(&mut *rng, &mut *state))
+    })?;
+    lua_ctx.globals().set("randomgaussian", randomgaussian)?;
+    let rng_snapshot = scope.create_function(move |inner_lua_ctx, _args: Variadic<Value>| {
+        let snapshot = RngSnapshot {
+            streams: rng_streams.borrow().snapshot(),
+            gaussian: gaussian_rng.borrow().get_state(),
+            gaussian_has_value: gaussian_state.borrow().has_value,
+            gaussian_value: gaussian_state.borrow().value
+        };
+        inner_lua_ctx.create_userdata(snapshot)
+    })?;
+    lua_ctx.globals().set("RngSnapshot", rng_snapshot)?;
+    let rng_restore = scope.create_function(move |_, snap: AnyUserData| {
+        let snapshot = snap.borrow::<RngSnapshot>()?;
+        rng_streams.borrow_mut().restore(&snapshot.streams);
+        gaussian_rng.borrow_mut().set_state(snapshot.gaussian);
+        let mut state = gaussian_state.borrow_mut();
+        state.has_value = snapshot.gaussian_has_value;
+        state.value = snapshot.gaussian_value;
+        Ok(())
+    })?;
+    lua_ctx.globals().set("RngRestore", rng_restore)?;
+    Ok(())
+}
+
+// Loads the save's decompressed lua_state globals (already parsed once by the caller)
+// into this context, skipping anything the game itself ignores on load.
+const SAVE_GLOBALS_SRC: &str = r#"
+    for _,savedValues in pairs(RouteFinderSaveFileData) do
+      for key, value in pairs(savedValues) do
+        if not SaveIgnores[key] then
+          _G[key] = value
+        end
+      end
+    end
+    "#;
+
+fn load_save_globals(lua_ctx: Context, lua_state: &[u8]) -> Result<()> {
+    let mut remaining = lua_state;
+    match luabins::load(&mut remaining, lua_ctx, "luabins".to_string()) {
+      Ok(vec) => lua_ctx.globals().set("RouteFinderSaveFileData", vec)?,
+      Err(s) => println!("{}", s)
+    };
+    lua_ctx.load(SAVE_GLOBALS_SRC).exec()?;
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let args = Cli::from_args();
+
+    // Read the engine/main/room-manager scripts, the user script and the save from disk
+    // exactly once; a seed search shares these bytes with each worker through an Arc
+    // instead of re-reading them for every candidate seed. Files loaded through Import
+    // are still read from disk by each context that imports them.
+    let engine_src = read_file("Engine.lua")?;
+    let mut main_path = args.hades_scripts_dir.clone();
+    main_path.push("Main.lua");
+    let main_src = read_file(main_path)?;
+    let mut room_manager_path = args.hades_scripts_dir.clone();
+    room_manager_path.push("RoomManager.lua");
+    let room_manager_src = read_file(room_manager_path)?;
+    let script_src = read_file(&args.script)?;
+
+    let save_file = read_file(&args.hades_save_file)?;
+    let lua_state_lz4 = match save::read(&mut save_file.as_slice(), "save".to_string()) {
+      Ok(save_file) => save_file.lua_state_lz4,
+      Err(s) => {
+        println!("error reading save: {}", s);
+        Vec::new()
+      }
+    };
+    let lua_state = match lz4::block::decompress(&lua_state_lz4.as_slice(), Some(save::HadesSaveV16::UNCOMPRESSED_SIZE)) {
+      Ok(uncompressed) => uncompressed,
+      Err(e) => {
+        println!("{}", e);
+        Vec::new()
+      }
+    };
+
+    match &args.seed_range {
+      Some(range) => run_seed_search(
+        &args,
+        range,
+        Arc::new(engine_src),
+        Arc::new(main_src),
+        Arc::new(room_manager_src),
+        Arc::new(lua_state),
+        Arc::new(script_src)
+      ),
+      None => run_single(&args, &engine_src, &main_src, &room_manager_src, &lua_state, &script_src)
+    }
+}
+
+fn run_single(
+  args: &Cli,
+  engine_src: &[u8],
+  main_src: &[u8],
+  room_manager_src: &[u8],
+  lua_state: &[u8],
+  script_src: &[u8]
+) -> Result<()> {
     let lua = unsafe {
       Lua::new_with_debug()
     };
-    let shared_rng = Rc::new(RefCell::new(SggPcg::new(0)));
+    let rng_streams = Rc::new(RefCell::new(RngStreams::new(0)));
+    // Deliberately separate from rng_streams and seeded once: the real game's gaussian
+    // generator persists across RandomSeed/RandomSynchronize instead of following them.
+    let gaussian_rng = Rc::new(RefCell::new(SggPcg::new(args.gaussian_seed)));
+    let gaussian_state = Rc::new(RefCell::new(GaussState { has_value: false, value: 0.0 }));
     let parent_path = args.hades_scripts_dir.clone();
     lua.context(|lua_ctx| {
+        let modules = Rc::new(RefCell::new(HashMap::new()));
+        let loading = Rc::new(RefCell::new(Vec::new()));
         lua_ctx.scope(|scope| {
-            let import = scope.create_function(|inner_lua_ctx, import_str: String| {
-                let import_file = read_file(parent_path.clone().join(import_str))?;
-                inner_lua_ctx.load(&import_file).exec()
-            })?;
-            lua_ctx.globals().set("Import", import)?;
-            // Engine callbacks etc.
-            let engine = read_file("Engine.lua")?;
-            lua_ctx.load(&engine).exec()?;
-            // Hooks into the engine for RNG
-            let randomseed = scope.create_function(|_, (o_seed, _id): (Option<i32>, Value) | {
-                let seed = match o_seed {
-                    Some(s) => s,
-                    None => 0
-                };
-                let mut rng = shared_rng.borrow_mut(); 
-                *rng = SggPcg::new(seed as u64);
-                Ok(())
-            })?;
-            lua_ctx.globals().set("randomseed", randomseed)?;
-            let randomint = scope.create_function(|_, (min, max, _id): (i32, i32, Value)| {
-                let mut rng = shared_rng.borrow_mut();
-                Ok(rand_int(&mut *rng, min, max))
-            })?;
-            lua_ctx.globals().set("randomint", randomint)?;
-            let random = scope.create_function(|_, _args: Variadic<Value>| {
-                let mut rng = shared_rng.borrow_mut();
-                Ok(rand_double(&mut *rng))
-            })?;
-            lua_ctx.globals().set("random", random)?;
-            let randomgaussian = scope.create_function(|_, _args: Variadic<Value>| {
-                Ok(0.0) // only affects enemy ratios in encounters, but not number of waves or types
-            })?;
-            lua_ctx.globals().set("randomgaussian", randomgaussian)?;
-            // Load lua files
-            let mut main_path = args.hades_scripts_dir.clone();
-            main_path.push("Main.lua");
-            let main = read_file(main_path)?;
-            lua_ctx.load(&main).exec()?;
-            let mut room_manager_path = args.hades_scripts_dir.clone();
-            room_manager_path.push("RoomManager.lua");
-            let room_manager = read_file(room_manager_path)?;
-            lua_ctx.load(&room_manager).exec()?;
-            let save_file = read_file(args.hades_save_file)?;
-            let lua_state_lz4 = match save::read(&mut save_file.as_slice(), "save".to_string()) {
-              Ok(save_file) => save_file.lua_state_lz4,
-              Err(s) => {
-                println!("error reading save: {}", s);
-                Vec::new()
-              }
-            };
-            let lua_state = match lz4::block::decompress(&lua_state_lz4.as_slice(), Some(save::HadesSaveV16::UNCOMPRESSED_SIZE)) {
-              Ok(uncompressed) => {
-                uncompressed
-              },
-              Err(e) => {
-                println!("{}", e);
-                Vec::new()
-              }
-            };
-            match luabins::load(&mut lua_state.as_slice(), lua_ctx, "luabins".to_string()) {
-              Ok(vec) => lua_ctx.globals().set("RouteFinderSaveFileData", vec)?,
-              Err(s) => println!("{}", s)
-            };
-            // put save file data into globals
-            lua_ctx.load(r#"
-                for _,savedValues in pairs(RouteFinderSaveFileData) do
-                  for key, value in pairs(savedValues) do
-                    if not SaveIgnores[key] then
-                      _G[key] = value
-                    end
-                  end
-                end
-                "#).exec()?;
+            install_hooks(lua_ctx, scope, parent_path, &rng_streams, &gaussian_rng, &gaussian_state, &modules, &loading)?;
+            lua_ctx.load(engine_src).exec()?;
+            lua_ctx.load(main_src).exec()?;
+            lua_ctx.load(room_manager_src).exec()?;
+            load_save_globals(lua_ctx, lua_state)?;
             // load and run script
-            let script = read_file(args.script)?;
-            lua_ctx.load(&script).exec()
+            lua_ctx.load(script_src).exec()
         })?;
         Ok(())
     })
 }
 
-const BYTE_ORDER_MARK: &[u8] = "\u{feff}".as_bytes();
-fn read_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
-  let file = fs::read(path)?;
-  if file.starts_with(BYTE_ORDER_MARK) {
-     Ok(file[3..].to_vec())
-  } else {
-     Ok(file.to_vec())
-  }
+struct SeedHit {
+  seed: u32,
+  detail: String
 }
 
+struct WorkerInput {
+  scripts_dir: PathBuf,
+  gaussian_seed: u64,
+  engine_src: Arc<Vec<u8>>,
+  main_src: Arc<Vec<u8>>,
+  room_manager_src: Arc<Vec<u8>>,
+  lua_state: Arc<Vec<u8>>,
+  script_src: Arc<Vec<u8>>,
+  seeds: Vec<u32>
+}
 
-fn rand_int(rng: &mut SggPcg, min: i32, max: i32) -> i32 {
-  if max > min {
-    let bound = (max as u32).wrapping_sub(min as u32).wrapping_add(1);
-    min.wrapping_add(bounded(rng, bound) as i32)
-  } else {
-    min
-  }
+// Each worker owns its own Lua (rlua's Context isn't Send), loads the scripts and save
+// globals exactly once, then replays the script body once per seed in its slice, calling
+// randomseed(seed) first so the run starts from a clean, reproducible RNG state.
+fn run_worker(input: WorkerInput) -> Vec<SeedHit> {
+    match run_worker_inner(&input) {
+      Ok(hits) => hits,
+      Err(e) => {
+        println!("worker error: {:?}", e);
+        Vec::new()
+      }
+    }
 }
 
-fn bounded(rng: &mut SggPcg, bound: u32) -> u32 {
-  let threshold = (u32::MAX - bound + 1) % bound;
+fn run_worker_inner(input: &WorkerInput) -> Result<Vec<SeedHit>> {
+    let lua = unsafe {
+      Lua::new_with_debug()
+    };
+    let rng_streams = Rc::new(RefCell::new(RngStreams::new(0)));
+    let gaussian_rng = Rc::new(RefCell::new(SggPcg::new(input.gaussian_seed)));
+    let gaussian_state = Rc::new(RefCell::new(GaussState { has_value: false, value: 0.0 }));
+    let parent_path = input.scripts_dir.clone();
+    let mut hits = Vec::new();
+    lua.context(|lua_ctx| -> Result<()> {
+        let modules = Rc::new(RefCell::new(HashMap::new()));
+        let loading = Rc::new(RefCell::new(Vec::new()));
+        lua_ctx.scope(|scope| {
+            install_hooks(lua_ctx, scope, parent_path, &rng_streams, &gaussian_rng, &gaussian_state, &modules, &loading)?;
+            lua_ctx.load(&input.engine_src).exec()?;
+            lua_ctx.load(&input.main_src).exec()?;
+            lua_ctx.load(&input.room_manager_src).exec()?;
+            load_save_globals(lua_ctx, &input.lua_state)?;
+            let randomseed: rlua::Function = lua_ctx.globals().get("randomseed")?;
+            let tostring: rlua::Function = lua_ctx.globals().get("tostring")?;
+            for seed in &input.seeds {
+                // Reset the state the previous seed could have touched but that
+                // randomseed(seed) alone doesn't cover (the gaussian generator, named
+                // streams, and any modules cached by Import), so RNG draws don't
+                // depend on how many seeds came before in this worker's slice. Lua
+                // globals that the script itself changes are not reset and carry
+                // over to the next seed.
+                *rng_streams.borrow_mut() = RngStreams::new(0);
+                *gaussian_rng.borrow_mut() = SggPcg::new(input.gaussian_seed);
+                *gaussian_state.borrow_mut() = GaussState { has_value: false, value: 0.0 };
+                modules.borrow_mut().clear();
+                loading.borrow_mut().clear();
 
-  loop {
-    let r = rng.next_u32();
-    if r >= threshold {
-      return r % bound;
-    }
-  }
+                // A Lua runtime error on one seed (e.g. a script bug that only
+                // surfaces for certain RNG draws) must not cost the rest of this
+                // worker's chunk: log it against its seed and move on, instead of
+                // propagating out of the loop and silently dropping every hit and
+                // every remaining seed this worker hasn't tried yet.
+                let seed_result: rlua::Result<()> = (|| {
+                    lua_ctx.globals().set("RouteFinderHit", Value::Nil)?;
+                    randomseed.call::<_, ()>(*seed)?;
+                    lua_ctx.load(&input.script_src).exec()?;
+                    let hit: Value = lua_ctx.globals().get("RouteFinderHit")?;
+                    if !matches!(hit, Value::Nil) {
+                      let detail: String = tostring.call(hit)?;
+                      hits.push(SeedHit { seed: *seed, detail });
+                    }
+                    Ok(())
+                })();
+                if let Err(e) = seed_result {
+                  println!("seed {} error: {:?}", seed, e);
+                }
+            }
+            Ok(())
+        })
+    })?;
+    Ok(hits)
 }
 
-fn rand_double(rng: &mut SggPcg) -> f64 {
-  ldexp(rng.next_u32() as f64, -32)
+// Seeds are passed to the Lua randomseed/randomsynchronize hooks as u32, so a range
+// outside that span can never be seeded faithfully; reject it up front instead of
+// silently wrapping.
+fn parse_seed_range(range: &str) -> Result<(u32, u32)> {
+  let mut parts = range.splitn(2, "..");
+  let start = parts.next().unwrap_or("");
+  let end = parts.next().unwrap_or("");
+  let parse_bound = |s: &str| -> Result<u32> {
+    s.trim().parse::<u32>().map_err(|_| Error::Cli {
+      message: format!("invalid seed range {:?}, expected START..END with both bounds in 0..={}", range, u32::MAX)
+    })
+  };
+  Ok((parse_bound(start)?, parse_bound(end)?))
 }
 
-/* Rough stab at how random gaussian generate works in the Hades code.
-   - seems to be an independant SggPcg used only for gaussians
-   - the gaussian pcg isn't reseeded on RandomSeed or reset on RandomSynchronize
-   - it does seem to be reset to the same value every time when starting the game
+fn run_seed_search(
+  args: &Cli,
+  range: &str,
+  engine_src: Arc<Vec<u8>>,
+  main_src: Arc<Vec<u8>>,
+  room_manager_src: Arc<Vec<u8>>,
+  lua_state: Arc<Vec<u8>>,
+  script_src: Arc<Vec<u8>>
+) -> Result<()> {
+    let (start, end) = parse_seed_range(range)?;
+    let seeds: Vec<u32> = (start..end).collect();
+    let thread_count = args.threads.max(1);
+    let chunk_size = (seeds.len() + thread_count - 1) / thread_count;
 
-struct GaussState {
-  has_value: bool,
-  value: f64
+    let handles: Vec<_> = seeds.chunks(chunk_size.max(1)).map(|chunk| {
+        let input = WorkerInput {
+            scripts_dir: args.hades_scripts_dir.clone(),
+            gaussian_seed: args.gaussian_seed,
+            engine_src: engine_src.clone(),
+            main_src: main_src.clone(),
+            room_manager_src: room_manager_src.clone(),
+            lua_state: lua_state.clone(),
+            script_src: script_src.clone(),
+            seeds: chunk.to_vec()
+        };
+        std::thread::spawn(move || run_worker(input))
+    }).collect();
+
+    let mut hits: Vec<SeedHit> = Vec::new();
+    for handle in handles {
+        match handle.join() {
+          Ok(mut worker_hits) => hits.append(&mut worker_hits),
+          Err(_) => println!("seed search worker panicked")
+        }
+    }
+    hits.sort_by_key(|hit| hit.seed);
+    hits.dedup_by_key(|hit| hit.seed);
+
+    println!("found {} hit(s) across seeds {}..{}", hits.len(), start, end);
+    for hit in &hits {
+        println!("seed {}: {}", hit.seed, hit.detail);
+    }
+    Ok(())
+}
+
+const BYTE_ORDER_MARK: &[u8] = "\u{feff}".as_bytes();
+fn read_file<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
+  let file = fs::read(path)?;
+  if file.starts_with(BYTE_ORDER_MARK) {
+     Ok(file[3..].to_vec())
+  } else {
+     Ok(file.to_vec())
+  }
 }
 
+
+// Polar (Marsaglia) Box-Muller, matching the game's gaussian generator:
+// - drawn from an independent SggPcg used only for gaussians
+// - not reseeded on RandomSeed or reset on RandomSynchronize
+// - reset to the same fixed value every time the game starts
 fn rand_gauss(rng: &mut SggPcg, state: &mut GaussState) -> f64 {
   if state.has_value {
-      state.has_value = false;
-      state.value
-   } else {
-      let mut u: f64 = 0.0;
-      let mut v: f64 = 0.0;
-      let mut s: f64 = 0.0;
-
-      // Box-Muller, polar form
-      while s >= 1.0 || s == 0.0 {
-        u = 2.0 * rand_double(rng) - 1.0;
-        v = 2.0 * rand_double(rng) - 1.0;
-        s = u * u + v * v;
+    state.has_value = false;
+    state.value
+  } else {
+    let mut u: f64;
+    let mut v: f64;
+    let mut s: f64;
+
+    loop {
+      u = 2.0 * rng.next_f64() - 1.0;
+      v = 2.0 * rng.next_f64() - 1.0;
+      s = u * u + v * v;
+      if s > 0.0 && s < 1.0 {
+        break;
       }
+    }
 
-      let f = libm::sqrt(-2.0 * libm::log(s) / s);
-      state.has_value = true; // keep for next call
-      state.value = f * u;
-      f * v
+    let f = libm::sqrt(-2.0 * libm::log(s) / s);
+    state.has_value = true; // keep for next call
+    state.value = f * u;
+    f * v
   }
 }
-*/
@@ -0,0 +1,120 @@
+// PCG32 (XSH-RR), matching the variant Supergiant's engine uses for its Lua RNG hooks.
+
+use libm::ldexp;
+use rand_core::{Error, RngCore, SeedableRng};
+
+const MULTIPLIER: u64 = 6364136223846793005;
+const DEFAULT_INC: u64 = 1442695040888963407;
+
+pub struct SggPcg {
+  state: u64,
+  inc: u64
+}
+
+/// A snapshot of a single `SggPcg`'s internal state, cheap to copy so it can be
+/// stashed away and restored later without disturbing the generator it came from.
+#[derive(Clone, Copy)]
+pub struct PcgState {
+  state: u64,
+  inc: u64
+}
+
+impl SggPcg {
+  pub fn new(seed: u64) -> Self {
+    let mut rng = SggPcg { state: 0, inc: DEFAULT_INC };
+    rng.step();
+    rng.state = rng.state.wrapping_add(seed);
+    rng.step();
+    rng
+  }
+
+  fn step(&mut self) {
+    self.state = self.state.wrapping_mul(MULTIPLIER).wrapping_add(self.inc);
+  }
+
+  pub fn next_u32(&mut self) -> u32 {
+    let old_state = self.state;
+    self.step();
+    let xorshifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+    let rot = (old_state >> 59) as u32;
+    xorshifted.rotate_right(rot)
+  }
+
+  pub fn get_state(&self) -> PcgState {
+    PcgState { state: self.state, inc: self.inc }
+  }
+
+  pub fn set_state(&mut self, snapshot: PcgState) {
+    self.state = snapshot.state;
+    self.inc = snapshot.inc;
+  }
+}
+
+impl RngCore for SggPcg {
+  fn next_u32(&mut self) -> u32 {
+    self.next_u32()
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    rand_core::impls::next_u64_via_u32(self)
+  }
+
+  fn fill_bytes(&mut self, dest: &mut [u8]) {
+    rand_core::impls::fill_bytes_via_next(self, dest)
+  }
+
+  fn try_fill_bytes(&mut self, dest: &mut [u8]) -> core::result::Result<(), Error> {
+    self.fill_bytes(dest);
+    Ok(())
+  }
+}
+
+impl SeedableRng for SggPcg {
+  type Seed = [u8; 8];
+
+  fn from_seed(seed: Self::Seed) -> Self {
+    SggPcg::new(u64::from_le_bytes(seed))
+  }
+}
+
+/// Blanket-implemented draw helpers for anything that can fill a byte buffer, so the
+/// bounded-int/double logic the hooks need is reusable and swappable for an alternate
+/// engine (e.g. for A/B verification against captured game traces).
+pub trait RandomSource {
+  fn fill_bytes(&mut self, dest: &mut [u8]);
+
+  fn next_u32(&mut self) -> u32 {
+    let mut buf = [0u8; 4];
+    self.fill_bytes(&mut buf);
+    u32::from_le_bytes(buf)
+  }
+
+  fn next_f64(&mut self) -> f64 {
+    ldexp(self.next_u32() as f64, -32)
+  }
+
+  fn bounded_u32(&mut self, bound: u32) -> u32 {
+    let threshold = (u32::MAX - bound + 1) % bound;
+    loop {
+      let r = self.next_u32();
+      if r >= threshold {
+        return r % bound;
+      }
+    }
+  }
+
+  fn bounded_i32(&mut self, min: i32, max: i32) -> i32 {
+    if max > min {
+      let bound = (max as u32).wrapping_sub(min as u32).wrapping_add(1);
+      min.wrapping_add(self.bounded_u32(bound) as i32)
+    } else {
+      min
+    }
+  }
+}
+
+impl<T: RngCore> RandomSource for T {
+  fn fill_bytes(&mut self, dest: &mut [u8]) {
+    RngCore::fill_bytes(self, dest)
+  }
+}